@@ -1,67 +1,451 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::collections::HashMap;
 use std::env;
-use tauri::Emitter;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use notify::event::EventKind;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+
+/// Active file watchers keyed by the path as passed to `watch_file`. Re-watching
+/// a path replaces its handle, which stops the previous watcher on drop.
+#[derive(Default)]
+struct WatcherState(Mutex<HashMap<PathBuf, RecommendedWatcher>>);
+
+/// Payload emitted on the `file-changed` event when a watched file is rewritten.
+#[derive(Clone, Serialize)]
+struct FileChanged {
+    path: String,
+    content: String,
+}
+
+/// A single entry returned by [`list_markdown_files`], mirroring the shape a
+/// Tauri file browser front-end expects for building a tree view.
+#[derive(Clone, Serialize)]
+struct FileEntry {
+    name: String,
+    path: String,
+    size: u64,
+    is_directory: bool,
+    modified: Option<u64>,
+    created: Option<u64>,
+}
+
+/// Files discovered before the front-end signalled readiness. The list is held
+/// here until [`frontend_ready`] fires, at which point it is flushed as a single
+/// `tauri://file-args` event. This replaces the old fixed-delay `thread::sleep`
+/// guess about when the window is ready.
+#[derive(Default)]
+struct FileQueue(Mutex<Vec<String>>);
 
 #[tauri::command]
 fn get_command_line_args() -> Vec<String> {
     env::args().collect()
 }
 
+/// Collect the supported file paths from a set of process arguments, skipping
+/// the executable path in `argv[0]`.
+fn supported_files_from_args(args: &[String], config: &ExtensionConfig) -> Vec<String> {
+    args.iter()
+        .skip(1)
+        .filter(|path| config.is_supported(path))
+        .cloned()
+        .collect()
+}
+
+/// Called by the front-end once it has mounted and registered its event
+/// listeners. Any files queued during startup are flushed now, so nothing is
+/// emitted into the void before the listener exists.
+#[tauri::command]
+fn frontend_ready(app: AppHandle, queue: State<FileQueue>) {
+    let files = std::mem::take(&mut *lock(&queue.0));
+    if !files.is_empty() {
+        let _ = app.emit("tauri://file-args", &files);
+    }
+}
+
+/// How the front-end should present a file's contents.
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum RenderMode {
+    /// Render as markdown.
+    Markdown,
+    /// Show verbatim, with no markdown processing.
+    PlainText,
+}
+
+/// The kind of a file as reported by [`detect_file_kind`], including the
+/// `Unsupported` case so the front-end can react to files it cannot open.
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+enum FileKind {
+    Markdown,
+    PlainText,
+    Unsupported,
+}
+
+/// Extension → render-mode mapping. Loaded once at startup and held as managed
+/// state; it is the single source of truth for which files MarkReview opens and
+/// how each is displayed, so every entry point stays in sync.
+struct ExtensionConfig {
+    modes: HashMap<String, RenderMode>,
+}
+
+impl Default for ExtensionConfig {
+    fn default() -> Self {
+        let modes = HashMap::from([
+            ("md".to_string(), RenderMode::Markdown),
+            ("markdown".to_string(), RenderMode::Markdown),
+            ("txt".to_string(), RenderMode::PlainText),
+        ]);
+        Self { modes }
+    }
+}
+
+impl ExtensionConfig {
+    /// Load the config, layering any overrides from the JSON file named by the
+    /// `MARKREVIEW_CONFIG` environment variable on top of the built-in
+    /// defaults. Each entry maps a lowercase extension (no leading dot) to
+    /// either `"markdown"` or `"plainText"`; a missing or malformed file leaves
+    /// the defaults untouched.
+    fn load() -> Self {
+        let mut config = Self::default();
+        if let Some(overrides) = env::var("MARKREVIEW_CONFIG")
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|raw| serde_json::from_str::<HashMap<String, String>>(&raw).ok())
+        {
+            for (ext, mode) in overrides {
+                let mode = match mode.to_lowercase().as_str() {
+                    "markdown" | "md" => RenderMode::Markdown,
+                    _ => RenderMode::PlainText,
+                };
+                config.modes.insert(ext.to_lowercase(), mode);
+            }
+        }
+        config
+    }
+
+    /// The configured render mode for `path`, if its extension is known.
+    fn mode_for(&self, path: &str) -> Option<RenderMode> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?.to_lowercase();
+        self.modes.get(&ext).copied()
+    }
+
+    fn kind_for(&self, path: &str) -> FileKind {
+        match self.mode_for(path) {
+            Some(RenderMode::Markdown) => FileKind::Markdown,
+            Some(RenderMode::PlainText) => FileKind::PlainText,
+            None => FileKind::Unsupported,
+        }
+    }
+
+    fn is_supported(&self, path: &str) -> bool {
+        self.mode_for(path).is_some()
+    }
+}
+
+/// Managed wrapper so the extension config can be reloaded at runtime.
+struct ConfigState(Mutex<ExtensionConfig>);
+
+/// Report how `path` should be displayed, so the front-end can pick between
+/// markdown rendering and verbatim text.
+#[tauri::command]
+fn detect_file_kind(path: String, config: State<ConfigState>) -> FileKind {
+    lock(&config.0).kind_for(&path)
+}
+
+/// Reload the extension config from disk so users can add extensions without
+/// restarting the app.
+#[tauri::command]
+fn reload_config(config: State<ConfigState>) {
+    *lock(&config.0) = ExtensionConfig::load();
+}
+
+/// List the supported markdown files in `directory`, returning subdirectories
+/// too so the front-end can present a navigable folder tree. Unsupported files
+/// are filtered out; directories are always kept regardless of extension.
+#[tauri::command]
+fn list_markdown_files(
+    directory: String,
+    config: State<ConfigState>,
+) -> Result<Vec<FileEntry>, String> {
+    let entries = std::fs::read_dir(&directory)
+        .map_err(|e| format!("failed to read {directory}: {e}"))?;
+    let config = lock(&config.0);
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_directory = path.is_dir();
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if !is_directory && !config.is_supported(&name) {
+            continue;
+        }
+
+        let metadata = entry.metadata().ok();
+        files.push(FileEntry {
+            name,
+            path: path.to_string_lossy().into_owned(),
+            size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+            is_directory,
+            modified: metadata.as_ref().and_then(|m| unix_millis(m.modified().ok())),
+            created: metadata.as_ref().and_then(|m| unix_millis(m.created().ok())),
+        });
+    }
+
+    Ok(files)
+}
+
+/// Convert an optional [`std::time::SystemTime`] to unix milliseconds.
+fn unix_millis(time: Option<std::time::SystemTime>) -> Option<u64> {
+    time.and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+}
+
+/// Start watching `path` for changes and emit `file-changed` on every edit.
+///
+/// Editors often save atomically (write a temp file, then rename it over the
+/// target), so the parent directory is watched rather than the file itself;
+/// this keeps the watch alive across the rename-and-replace. Events are
+/// debounced so a burst of temp-write/rename notifications coalesces into a
+/// single re-read. When the file disappears a `file-removed` event is emitted
+/// but the watcher is retained, so a later re-creation of the path is still
+/// observed.
+#[tauri::command]
+fn watch_file(path: String, app: AppHandle, state: State<WatcherState>) -> Result<(), String> {
+    let target = PathBuf::from(&path);
+    let watch_dir = target
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("failed to create watcher: {e}"))?;
+    watcher
+        .watch(&watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("failed to watch {}: {e}", watch_dir.display()))?;
+
+    // Debounce loop: coalesce events that land within ~200ms of each other so a
+    // single logical save does not fire multiple re-reads.
+    let emit_target = target.clone();
+    let debounce = Duration::from_millis(200);
+    std::thread::spawn(move || {
+        let mut pending: Option<Instant> = None;
+        loop {
+            let event = match pending {
+                Some(first) => {
+                    let elapsed = first.elapsed();
+                    if elapsed >= debounce {
+                        flush(&app, &emit_target);
+                        pending = None;
+                        continue;
+                    }
+                    rx.recv_timeout(debounce - elapsed)
+                }
+                None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+            };
+
+            match event {
+                Ok(Ok(event)) if affects(&event, &emit_target) => {
+                    pending = Some(Instant::now());
+                }
+                Ok(_) => {}
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    flush(&app, &emit_target);
+                    pending = None;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+
+    lock(&state.0).insert(target, watcher);
+    Ok(())
+}
+
+/// Stop watching `path`, dropping its watcher if one is active.
+#[tauri::command]
+fn stop_watching(path: String, state: State<WatcherState>) {
+    lock(&state.0).remove(&PathBuf::from(path));
+}
+
+/// Does `event` concern the file we are actually watching? The parent directory
+/// watch sees siblings too, so filter by path (a rename reports both sides).
+fn affects(event: &notify::Event, target: &PathBuf) -> bool {
+    let relevant = matches!(
+        event.kind,
+        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_)
+    );
+    relevant && event.paths.iter().any(|p| p == target)
+}
+
+/// Re-read the target and emit `file-changed`, or `file-removed` if it is gone.
+fn flush(app: &AppHandle, target: &PathBuf) {
+    match std::fs::read_to_string(target) {
+        Ok(content) => {
+            let _ = app.emit(
+                "file-changed",
+                FileChanged {
+                    path: target.to_string_lossy().into_owned(),
+                    content,
+                },
+            );
+        }
+        Err(_) => {
+            let _ = app.emit("file-removed", target.to_string_lossy().into_owned());
+        }
+    }
+}
+
+/// Handle to the running export child process, so an in-flight conversion can
+/// be cancelled. Only one export runs at a time; starting a new one while
+/// another is running is refused.
+#[derive(Default)]
+struct ExportState(Mutex<Option<CommandChild>>);
+
+/// Run the bundled `pandoc` sidecar to convert `path` into `format`, streaming
+/// the converter's output back to the UI.
+///
+/// Each stdout/stderr line is emitted as an `export-progress` event; the run
+/// ends with either `export-done` carrying the output path or `export-error`
+/// carrying a message. The input path is validated against the extension config
+/// so a crafted path cannot be used to feed arbitrary files into the converter.
+#[tauri::command]
+async fn export_document(
+    path: String,
+    format: String,
+    app: AppHandle,
+    state: State<'_, ExportState>,
+    config: State<'_, ConfigState>,
+) -> Result<(), String> {
+    if !lock(&config.0).is_supported(&path) {
+        let message = format!("unsupported input file: {path}");
+        let _ = app.emit("export-error", &message);
+        return Err(message);
+    }
+
+    // Only one export may run at a time: refuse to start a second while one is
+    // in flight, so its handle in `state` stays valid for `cancel_export`.
+    if lock(&state.0).is_some() {
+        let message = "an export is already running".to_string();
+        let _ = app.emit("export-error", &message);
+        return Err(message);
+    }
+
+    let output_path = PathBuf::from(&path)
+        .with_extension(&format)
+        .to_string_lossy()
+        .into_owned();
+
+    let sidecar = app
+        .shell()
+        .sidecar("pandoc")
+        .map_err(|e| format!("failed to resolve pandoc sidecar: {e}"))?;
+    // The writer is taken from the `-o` extension. pandoc has no `pdf` writer
+    // (PDF is produced from the `.pdf` output extension plus a PDF engine), so
+    // `-t` is passed for every format except `pdf`.
+    let mut args = vec![path.clone(), "-o".to_string(), output_path.clone()];
+    if !format.eq_ignore_ascii_case("pdf") {
+        args.push("-t".to_string());
+        args.push(format.clone());
+    }
+    let (mut rx, child) = sidecar
+        .args(args)
+        .spawn()
+        .map_err(|e| format!("failed to start pandoc: {e}"))?;
+
+    *lock(&state.0) = Some(child);
+
+    while let Some(event) = rx.recv().await {
+        match event {
+            CommandEvent::Stdout(line) | CommandEvent::Stderr(line) => {
+                let _ = app.emit("export-progress", String::from_utf8_lossy(&line).trim_end());
+            }
+            CommandEvent::Terminated(payload) => {
+                // Clear the now-dead handle so cancel_export doesn't touch it
+                // and the next export is allowed to start.
+                *lock(&state.0) = None;
+                if payload.code == Some(0) {
+                    let _ = app.emit("export-done", &output_path);
+                } else {
+                    let _ = app.emit(
+                        "export-error",
+                        format!("pandoc exited with status {:?}", payload.code),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Cancel the running export, if any, by killing the converter process.
+#[tauri::command]
+fn cancel_export(state: State<ExportState>) {
+    if let Some(child) = lock(&state.0).take() {
+        let _ = child.kill();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 fn main() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            // A second launch (e.g. opening more files from the file manager)
+            // forwards its paths to the already-running window instead of
+            // starting a duplicate process.
+            let files = supported_files_from_args(&argv, &lock(&app.state::<ConfigState>().0));
+            if !files.is_empty() {
+                let _ = app.emit("tauri://file-args", &files);
+            }
+        }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![get_command_line_args])
+        .manage(WatcherState::default())
+        .manage(FileQueue::default())
+        .manage(ExportState::default())
+        .manage(ConfigState(Mutex::new(ExtensionConfig::load())))
+        .invoke_handler(tauri::generate_handler![
+            get_command_line_args,
+            watch_file,
+            stop_watching,
+            list_markdown_files,
+            frontend_ready,
+            export_document,
+            cancel_export,
+            detect_file_kind,
+            reload_config
+        ])
         .setup(|app| {
-            // Get command line arguments and emit them to frontend
+            // Collect the supported files passed on the command line and queue
+            // them. They are flushed once the front-end calls `frontend_ready`,
+            // so the whole list is delivered as tabs with no timing guess.
             let args: Vec<String> = env::args().collect();
-            // Command line args processing
-            
-            // Tauri app setup
-            
-            // Skip the first argument (executable path) and check for file arguments
-            if args.len() > 1 {
-                let file_args: Vec<String> = args[1..].to_vec();
-                // Processing file arguments
-                
-                // Filter for supported file extensions
-                let supported_files: Vec<String> = file_args
-                    .into_iter()
-                    .filter(|path| {
-                        let lower_path = path.to_lowercase();
-                        let is_supported = lower_path.ends_with(".md") || 
-                                         lower_path.ends_with(".markdown") || 
-                                         lower_path.ends_with(".txt");
-                        // Path validation check
-                        is_supported
-                    })
-                    .collect();
-                
-                // Supported files identified
-                
-                if !supported_files.is_empty() {
-                    // Clone for async block
-                    let file_path = supported_files[0].clone();
-                    let app_handle = app.handle().clone();
-                    
-                    // Delay emission to ensure frontend is ready
-                    std::thread::spawn(move || {
-                        std::thread::sleep(std::time::Duration::from_millis(2000));
-                        // Emitting file-args event with delay
-                        let _result = app_handle.emit("tauri://file-args", &file_path);
-                    });
-                } else {
-                    // No supported files found
-                }
-            } else {
-                // No command line arguments provided
+            let supported_files =
+                supported_files_from_args(&args, &lock(&app.state::<ConfigState>().0));
+            if !supported_files.is_empty() {
+                *lock(&app.state::<FileQueue>().0) = supported_files;
             }
-            
+
             Ok(())
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
-}
\ No newline at end of file
+}